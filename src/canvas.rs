@@ -13,6 +13,104 @@ use tracing::{event, span, Level};
 
 #[cfg(target_os = "macos")]
 use objc::rc::autoreleasepool;
+#[cfg(target_os = "macos")]
+use std::sync::Mutex;
+#[cfg(target_os = "macos")]
+use std::time::Duration;
+
+#[cfg(target_os = "macos")]
+use metal::{CommandQueue, MetalDrawable, MetalLayer};
+
+// CAMetalLayer already triple-buffers its own backing textures; this just
+// bounds how many drawables we hold a reference to on our side so a slow
+// present doesn't pile up unbounded references to retired drawables.
+#[cfg(target_os = "macos")]
+const DRAWABLE_RING_SIZE: usize = 3;
+
+// Default budget for scratch textures/buffers skia is allowed to hold onto
+// between frames. Past this, the context evicts the least-recently-used
+// resources instead of growing VMem unbounded.
+#[cfg(target_os = "macos")]
+const DEFAULT_RESOURCE_CACHE_LIMIT_BYTES: usize = 96 * 1024 * 1024;
+
+// How many frames (calls of `present_frame`, `present` or the Metal
+// `draw_raw_rgb_scale` path) to let pass before asking the context to
+// actually purge unused resources. Doing this every frame would thrash the
+// cache; doing it never is how we got the leak in the first place.
+#[cfg(target_os = "macos")]
+const DEFAULT_CLEANUP_INTERVAL_FRAMES: u32 = 60;
+
+// Holds the pieces needed to present frames to an on-screen `CAMetalLayer`:
+// the layer itself, the command queue used to submit the present, and a
+// small ring of drawables kept alive until their command buffer has been
+// committed.
+#[cfg(target_os = "macos")]
+struct Swapchain {
+    layer: MetalLayer,
+    queue: CommandQueue,
+    in_flight: Mutex<Vec<Option<MetalDrawable>>>,
+    next_ring_slot: Mutex<usize>,
+}
+
+/// A persistent, fixed-size GPU texture created by `Canvas::create_texture`.
+/// Refill it in place with `Canvas::update_texture` instead of allocating a
+/// new `SkImage` every frame.
+#[cfg(target_os = "macos")]
+pub struct Texture {
+    backend_texture: skia_safe::gpu::BackendTexture,
+    // A `BackendTexture` is just a raw descriptor; skia does not track or
+    // free the GPU texture it describes on its own. We keep a handle to the
+    // context that created it so `Drop` can release it explicitly.
+    context: DirectContext,
+    width: i32,
+    height: i32,
+}
+
+#[cfg(target_os = "macos")]
+impl Texture {
+    pub fn width(&self) -> u32 {
+        self.width as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height as u32
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for Texture {
+    fn drop(&mut self) {
+        self.context.delete_backend_texture(&self.backend_texture);
+    }
+}
+
+/// Planar pixel layouts accepted by `Canvas::draw_yuv_planes`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YuvFormat {
+    /// A full-resolution Y plane plus a half-resolution, interleaved U/V
+    /// plane. What most hardware video decoders hand back.
+    Nv12,
+    /// A full-resolution Y plane plus separate half-resolution U and V
+    /// planes.
+    I420,
+}
+
+impl YuvFormat {
+    fn plane_count(self) -> usize {
+        match self {
+            YuvFormat::Nv12 => 2,
+            YuvFormat::I420 => 3,
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    fn plane_config(self) -> skia_safe::yuva_info::PlaneConfig {
+        match self {
+            YuvFormat::Nv12 => skia_safe::yuva_info::PlaneConfig::Y_UV,
+            YuvFormat::I420 => skia_safe::yuva_info::PlaneConfig::Y_U_V,
+        }
+    }
+}
 
 pub struct Canvas {
     surface: Surface,
@@ -21,6 +119,15 @@ pub struct Canvas {
     _context: Option<DirectContext>, // This is just stored for the lifetime of the canvas.
     #[cfg(target_os = "macos")]
     _backend: Option<BackendContext>,
+    #[cfg(target_os = "macos")]
+    resource_cache_limit: usize,
+    #[cfg(target_os = "macos")]
+    cleanup_interval_frames: u32,
+    #[cfg(target_os = "macos")]
+    frames_since_cleanup: u32,
+    #[cfg(target_os = "macos")]
+    swapchain: Option<Swapchain>,
+    profiler: crate::profiler::Profiler,
 }
 
 impl Canvas {
@@ -58,6 +165,7 @@ impl Canvas {
                 )
             };
             let mut context = DirectContext::new_metal(&backend, None).unwrap();
+            context.set_resource_cache_limit(DEFAULT_RESOURCE_CACHE_LIMIT_BYTES);
 
             let image_info = ImageInfo::new_n32_premul((width as i32, height as i32), None);
             let surface = Surface::new_render_target(
@@ -88,6 +196,100 @@ impl Canvas {
                 paint,
                 _context: Some(context),
                 _backend: Some(backend), // Make sure backend isn't dropped.
+                resource_cache_limit: DEFAULT_RESOURCE_CACHE_LIMIT_BYTES,
+                cleanup_interval_frames: DEFAULT_CLEANUP_INTERVAL_FRAMES,
+                frames_since_cleanup: 0,
+                swapchain: None,
+                profiler: crate::profiler::Profiler::new(),
+            })
+        })
+    }
+
+    /// Wraps a `CAMetalLayer` for on-screen presentation. Drawing still
+    /// happens against the canvas's own offscreen surface via
+    /// `skia_canvas()` / `draw_raw_rgb_scale()`; call `present()` once per
+    /// frame to composite that surface onto the layer's next drawable and
+    /// show it.
+    #[cfg(target_os = "macos")]
+    pub fn new_metal_layer(layer: MetalLayer, width: u32, height: u32) -> Option<Canvas> {
+        autoreleasepool(|| {
+            let span = span!(Level::INFO, "Canvas::new_metal_layer");
+            let _guard = span.enter();
+
+            use metal::Device;
+            use skia_safe::{gpu::SurfaceOrigin, Budgeted, ImageInfo};
+
+            let mut paint = Paint::default();
+            paint.set_color(Color::BLACK);
+            paint.set_stroke_width(1.0);
+            paint.set_blend_mode(skia_safe::BlendMode::SrcOver);
+
+            let device = Device::system_default();
+            if device.is_none() {
+                event!(
+                    Level::INFO,
+                    "Failed to create Metal device, falling back to CPU."
+                );
+                return None;
+            }
+            let device = device.unwrap();
+            let queue = device.new_command_queue();
+
+            layer.set_device(&device);
+            layer.set_pixel_format(metal::MTLPixelFormat::BGRA8Unorm);
+            layer.set_drawable_size(metal::CGSize::new(width as f64, height as f64));
+
+            let backend = unsafe {
+                mtl::BackendContext::new(
+                    device.as_ptr() as mtl::Handle,
+                    queue.as_ptr() as mtl::Handle,
+                    ptr::null(),
+                )
+            };
+            let mut context = DirectContext::new_metal(&backend, None).unwrap();
+            context.set_resource_cache_limit(DEFAULT_RESOURCE_CACHE_LIMIT_BYTES);
+
+            // Drawing itself still targets an ordinary offscreen surface;
+            // `present()` is what blits it onto the layer each frame.
+            let image_info = ImageInfo::new_n32_premul((width as i32, height as i32), None);
+            let surface = Surface::new_render_target(
+                &mut context,
+                Budgeted::Yes,
+                &image_info,
+                None,
+                SurfaceOrigin::TopLeft,
+                None,
+                false,
+            );
+            if surface.is_none() {
+                event!(
+                    Level::INFO,
+                    "Failed to create Metal surface, falling back to CPU."
+                );
+                return None;
+            }
+            let mut surface = surface.unwrap();
+            surface.canvas().clear(Color::RED);
+
+            let swapchain = Swapchain {
+                layer,
+                queue,
+                in_flight: Mutex::new((0..DRAWABLE_RING_SIZE).map(|_| None).collect()),
+                next_ring_slot: Mutex::new(0),
+            };
+
+            event!(Level::INFO, "Created new Metal layer backed canvas");
+
+            Some(Canvas {
+                surface,
+                paint,
+                _context: Some(context),
+                _backend: Some(backend),
+                resource_cache_limit: DEFAULT_RESOURCE_CACHE_LIMIT_BYTES,
+                cleanup_interval_frames: DEFAULT_CLEANUP_INTERVAL_FRAMES,
+                frames_since_cleanup: 0,
+                swapchain: Some(swapchain),
+                profiler: crate::profiler::Profiler::new(),
             })
         })
     }
@@ -112,6 +314,15 @@ impl Canvas {
             _context: None,
             #[cfg(target_os = "macos")]
             _backend: None,
+            #[cfg(target_os = "macos")]
+            resource_cache_limit: DEFAULT_RESOURCE_CACHE_LIMIT_BYTES,
+            #[cfg(target_os = "macos")]
+            cleanup_interval_frames: DEFAULT_CLEANUP_INTERVAL_FRAMES,
+            #[cfg(target_os = "macos")]
+            frames_since_cleanup: 0,
+            #[cfg(target_os = "macos")]
+            swapchain: None,
+            profiler: crate::profiler::Profiler::new(),
         }
     }
 
@@ -123,6 +334,280 @@ impl Canvas {
         self.surface.height() as usize
     }
 
+    /// Sets the byte budget skia is allowed to keep scratch GPU resources
+    /// within. Only meaningful for a Metal-backed canvas.
+    #[cfg(target_os = "macos")]
+    pub fn set_resource_cache_limit(&mut self, max_resource_bytes: usize) {
+        self.resource_cache_limit = max_resource_bytes;
+        if let Some(context) = self._context.as_mut() {
+            context.set_resource_cache_limit(max_resource_bytes);
+        }
+    }
+
+    /// Sets how many frames (calls of `present_frame`, `present` or the
+    /// Metal `draw_raw_rgb_scale` path) pass between deferred-cleanup passes
+    /// over the resource cache.
+    #[cfg(target_os = "macos")]
+    pub fn set_cleanup_interval_frames(&mut self, frames: u32) {
+        self.cleanup_interval_frames = frames.max(1);
+    }
+
+    // Submits the pending GPU work for this frame and runs the cleanup
+    // cadence below. Must run inside an autoreleasepool: on Metal a command
+    // buffer isn't actually freed until it's committed and its drawable
+    // released there.
+    #[cfg(target_os = "macos")]
+    fn flush_and_maybe_cleanup(&mut self) {
+        self.profiler.begin_gpu_submit();
+        self.surface.flush_and_submit();
+        self.profiler.end_gpu_submit();
+        self.maybe_cleanup();
+    }
+
+    // On the configured cadence, asks the context to purge resources it no
+    // longer needs. Shared by both presentation paths: `present_frame()`
+    // (offscreen surface) calls it via `flush_and_maybe_cleanup()` above,
+    // and `present()` (swapchain) calls it directly, since a swapchain-only
+    // app never goes through the former and would otherwise never exercise
+    // this cadence at all.
+    #[cfg(target_os = "macos")]
+    fn maybe_cleanup(&mut self) {
+        let Some(context) = self._context.as_mut() else {
+            return;
+        };
+        self.frames_since_cleanup += 1;
+        if self.frames_since_cleanup >= self.cleanup_interval_frames {
+            // `perform_deferred_cleanup` alone stops the leak: it purges
+            // resources that have been unused for the given duration (zero
+            // here, since we're already gating on `cleanup_interval_frames`)
+            // while respecting `resource_cache_limit`. `free_gpu_resources()`
+            // would additionally drop the *entire* purgeable cache on every
+            // cycle, including scratch textures still within budget, forcing
+            // them to be rebuilt next frame.
+            context.perform_deferred_cleanup(Duration::from_secs(0), None);
+            self.frames_since_cleanup = 0;
+        }
+    }
+
+    /// Submits queued GPU work for the current frame. Callers driving their
+    /// own draw loop (rather than going through `draw_raw_rgb_scale`) should
+    /// call this once per frame so skia can reclaim scratch textures instead
+    /// of holding every frame's intermediates.
+    #[cfg(target_os = "macos")]
+    pub fn present_frame(&mut self) {
+        let span = span!(Level::INFO, "Canvas::present_frame");
+        let _guard = span.enter();
+        autoreleasepool(|| self.flush_and_maybe_cleanup());
+    }
+
+    /// Composites the canvas's offscreen surface onto the next drawable of
+    /// the `CAMetalLayer` passed to `new_metal_layer()` and shows it, then
+    /// runs the same resource-cache cleanup cadence as `present_frame()` (a
+    /// swapchain-only caller never calls that, so this is the only place
+    /// that cadence gets exercised for it). Returns `false` (and skips the
+    /// frame) if the layer has no drawable available right now, e.g.
+    /// mid-resize or occluded.
+    #[cfg(target_os = "macos")]
+    pub fn present(&mut self) -> bool {
+        let span = span!(Level::INFO, "Canvas::present");
+        let _guard = span.enter();
+
+        autoreleasepool(|| {
+            let (Some(context), Some(swapchain)) =
+                (self._context.as_mut(), self.swapchain.as_ref())
+            else {
+                event!(Level::ERROR, "present() called on a non-swapchain canvas");
+                return false;
+            };
+
+            let Some(drawable) = swapchain.layer.next_drawable() else {
+                event!(Level::TRACE, "next_drawable() returned None, skipping frame");
+                return false;
+            };
+
+            let texture_info =
+                unsafe { mtl::TextureInfo::new(drawable.texture().as_ptr() as mtl::Handle) };
+            let drawable_size = swapchain.layer.drawable_size();
+            let backend_render_target = skia_safe::gpu::BackendRenderTarget::new_metal(
+                (drawable_size.width as i32, drawable_size.height as i32),
+                &texture_info,
+            );
+            let drawable_surface = Surface::from_backend_render_target(
+                context,
+                &backend_render_target,
+                skia_safe::gpu::SurfaceOrigin::TopLeft,
+                skia_safe::ColorType::BGRA8888,
+                None,
+                None,
+            );
+            let Some(mut drawable_surface) = drawable_surface else {
+                event!(Level::ERROR, "Failed to wrap drawable texture as a surface.");
+                return false;
+            };
+
+            let snapshot = self.surface.image_snapshot();
+            drawable_surface
+                .canvas()
+                .draw_image(&snapshot, (0, 0), Some(&self.paint));
+            drawable_surface.flush_and_submit();
+
+            let command_buffer = swapchain.queue.new_command_buffer();
+            command_buffer.present_drawable(&drawable);
+            command_buffer.commit();
+
+            // Keep the drawable alive until its command buffer has actually
+            // been committed above; park it round-robin in the ring so we
+            // never hold more than `DRAWABLE_RING_SIZE` of them at once.
+            let mut slot = swapchain.next_ring_slot.lock().unwrap();
+            let mut in_flight = swapchain.in_flight.lock().unwrap();
+            in_flight[*slot] = Some(drawable);
+            *slot = (*slot + 1) % in_flight.len();
+            drop(in_flight);
+            drop(slot);
+            self.maybe_cleanup();
+
+            true
+        })
+    }
+
+    /// Allocates a persistent, fixed-size BGRA8888 GPU texture. Reuse the
+    /// returned `Texture` across frames via `update_texture()` instead of
+    /// building a fresh `SkImage` (and its upload) every call.
+    #[cfg(target_os = "macos")]
+    pub fn create_texture(&mut self, width: u32, height: u32) -> Option<Texture> {
+        let span = span!(Level::INFO, "Canvas::create_texture");
+        let _guard = span.enter();
+
+        let context = self._context.as_mut()?;
+        let backend_texture = context.create_backend_texture(
+            (width as i32, height as i32),
+            skia_safe::ColorType::BGRA8888,
+            skia_safe::gpu::Mipmapped::No,
+            skia_safe::gpu::Renderable::No,
+            skia_safe::gpu::Protected::No,
+        )?;
+        Some(Texture {
+            backend_texture,
+            context: context.clone(),
+            width: width as i32,
+            height: height as i32,
+        })
+    }
+
+    /// Writes `pixels` into `texture`'s existing GPU allocation (via
+    /// `update_backend_texture`, i.e. no new scratch texture and no extra
+    /// upload buffer) and returns a cheap `Image` view of it. `pixels` is
+    /// always a full `w`-wide, `w * 4`-byte-stride BGRA8888 buffer (the
+    /// caller's current frame); `dirty_rect` limits the update to a
+    /// top-left-anchored sub-region of it (`None` updates the whole `w x h`
+    /// buffer). It must have its origin at `(0, 0)`, because
+    /// `update_backend_texture` always writes the given pixmap starting at
+    /// texel (0, 0) of the destination and has no x/y offset of its own, so
+    /// an arbitrary sub-rect can't be placed without silently landing in the
+    /// wrong spot. A narrower-than-`w` `dirty_rect` still reads `pixels` at
+    /// its real `w`-wide stride, not the narrower rect's width, so rows
+    /// after the first land on the right offset.
+    #[cfg(target_os = "macos")]
+    pub fn update_texture(
+        &mut self,
+        texture: &mut Texture,
+        w: u32,
+        h: u32,
+        pixels: &[u8],
+        dirty_rect: Option<skia_safe::IRect>,
+    ) -> Option<Image> {
+        let span = span!(Level::INFO, "Canvas::update_texture");
+        let _guard = span.enter();
+
+        // `rect` describes the region of `pixels` (the caller's *current*
+        // frame, sized w x h) being uploaded; it is validated against the
+        // texture's own dimensions below rather than assumed to match them.
+        let rect = dirty_rect.unwrap_or_else(|| skia_safe::IRect::new(0, 0, w as i32, h as i32));
+        if rect.left() != 0 || rect.top() != 0 {
+            event!(
+                Level::ERROR,
+                "update_texture: dirty_rect {:?} is not anchored at (0, 0); \
+                 update_backend_texture has no destination offset, so an \
+                 off-origin rect isn't supported.",
+                rect,
+            );
+            return None;
+        }
+        if rect.right() > texture.width || rect.bottom() > texture.height {
+            event!(
+                Level::ERROR,
+                "update_texture: dirty_rect {:?} exceeds texture bounds {}x{}.",
+                rect,
+                texture.width,
+                texture.height
+            );
+            return None;
+        }
+        if rect.right() > w as i32 || rect.bottom() > h as i32 {
+            event!(
+                Level::ERROR,
+                "update_texture: dirty_rect {:?} exceeds the {}x{} source buffer.",
+                rect,
+                w,
+                h
+            );
+            return None;
+        }
+
+        // `pixels` is always strided at the caller's full frame width `w`,
+        // not `rect.width()` -- a narrower dirty rect only trims how many of
+        // those full-stride rows/columns get uploaded, it doesn't repack the
+        // buffer. Using `rect.width()` here would read every row after the
+        // first at the wrong offset for anything narrower than `w`.
+        let row_bytes = w as usize * mem::size_of::<u32>();
+        let required_bytes = row_bytes * rect.height() as usize;
+        if pixels.len() < required_bytes {
+            event!(
+                Level::ERROR,
+                "update_texture: pixels buffer ({} bytes) is smaller than the {} bytes needed for a {}-row update at {}-byte stride.",
+                pixels.len(),
+                required_bytes,
+                rect.height(),
+                row_bytes
+            );
+            return None;
+        }
+
+        let info = skia_safe::ImageInfo::new(
+            (rect.width(), rect.height()),
+            skia_safe::ColorType::BGRA8888,
+            skia_safe::AlphaType::Premul,
+            None,
+        );
+        let pixmap = skia_safe::Pixmap::new(&info, pixels, row_bytes);
+
+        let context = self._context.as_mut()?;
+        let updated = context.update_backend_texture(
+            &texture.backend_texture,
+            &[pixmap],
+            skia_safe::gpu::SurfaceOrigin::TopLeft,
+            None,
+            None,
+        );
+        if !updated {
+            event!(Level::ERROR, "Failed to update backend texture in place.");
+            return None;
+        }
+        self.profiler.record(
+            crate::profiler::Counter::ImageUploadBytes,
+            (rect.width() as usize * rect.height() as usize * mem::size_of::<u32>()) as f64,
+        );
+
+        Image::from_texture(
+            context,
+            &texture.backend_texture,
+            skia_safe::gpu::SurfaceOrigin::TopLeft,
+            skia_safe::ColorType::BGRA8888,
+            skia_safe::AlphaType::Premul,
+            None,
+        )
+    }
+
     pub fn draw_raw_rgb_scale(
         &mut self,
         x: i32,
@@ -132,7 +617,19 @@ impl Canvas {
         scale: u32,
         pixels: Option<&[u8]>,
         image: Option<Image>,
+        #[cfg(target_os = "macos")] texture: Option<&mut Texture>,
     ) -> Option<Image> {
+        // A persistent texture takes priority: refresh its existing GPU
+        // allocation in place and draw that, rather than building a new
+        // `SkImage` (and paying for a fresh upload) this frame.
+        #[cfg(target_os = "macos")]
+        let image = match (texture, pixels) {
+            (Some(texture), Some(pixels)) => {
+                self.update_texture(texture, w, h, pixels, None).or(image)
+            }
+            _ => image,
+        };
+
         fn draw_raw_rgb_scale_internal(
             canvas: &mut Canvas,
             x: i32,
@@ -145,6 +642,7 @@ impl Canvas {
         ) -> Option<Image> {
             let span = span!(Level::INFO, "Canvas::draw_raw_rgb_scale");
             let _guard = span.enter();
+            canvas.profiler.begin_frame();
             let info = skia_safe::ImageInfo::new(
                 skia_safe::ISize::new(w as i32, h as i32),
                 skia_safe::ColorType::BGRA8888,
@@ -158,6 +656,9 @@ impl Canvas {
             let image = if let Some(image) = image {
                 Some(image)
             } else if let Some(pixels) = pixels {
+                canvas
+                    .profiler
+                    .record(crate::profiler::Counter::ImageUploadBytes, pixels.len() as f64);
                 let sdata = unsafe { skia_safe::Data::new_bytes(pixels) };
                 let image = skia_safe::Image::from_raster_data(
                     &info,
@@ -171,11 +672,19 @@ impl Canvas {
 
             if image.is_none() {
                 event!(Level::ERROR, "Failed to create image from raster data.");
+                canvas.profiler.end_frame();
                 return None;
             }
             let image = image.unwrap();
             let result = canvas.draw_image_scale(&image, x, y, scale);
             event!(Level::TRACE, "Draw image scale: {}", result);
+
+            // Submit inside the same autoreleasepool the draw happened in;
+            // on Metal the command buffer isn't freed until it's committed
+            // and its drawable released here.
+            #[cfg(target_os = "macos")]
+            canvas.flush_and_maybe_cleanup();
+            canvas.profiler.end_frame();
             Some(image)
         }
         #[cfg(target_os = "macos")]
@@ -241,4 +750,468 @@ impl Canvas {
     pub fn skia_canvas(&mut self) -> &mut skia_safe::Canvas {
         self.surface.canvas()
     }
+
+    /// The rolling frame profiler. `draw_raw_rgb_scale` feeds it CPU frame
+    /// time, GPU submit time and image-upload bytes automatically; use this
+    /// to read the counters back or to draw `overlay()` onto the surface.
+    #[inline]
+    pub fn profiler(&mut self) -> &mut crate::profiler::Profiler {
+        &mut self.profiler
+    }
+
+    /// Draws the profiler's `overlay()` onto the live surface at `origin`.
+    /// A separate method (rather than `profiler().overlay(skia_canvas(), ..)`)
+    /// because the two accessors would otherwise both need `&mut self`.
+    pub fn draw_profiler_overlay(&mut self, origin: skia_safe::Point) {
+        let Canvas {
+            surface, profiler, ..
+        } = self;
+        profiler.overlay(surface.canvas(), origin);
+    }
+
+    /// Starts recording draw commands instead of issuing them directly.
+    /// Draw into the `Recorder`'s canvas, then call
+    /// `Recorder::finish_recording()` to get back a replayable `Picture`.
+    pub fn begin_recording(&self) -> Recorder {
+        let span = span!(Level::INFO, "Canvas::begin_recording");
+        let _guard = span.enter();
+        let bounds = skia_safe::Rect::new(
+            0.0,
+            0.0,
+            self.surface.width() as f32,
+            self.surface.height() as f32,
+        );
+        let mut recorder = skia_safe::PictureRecorder::new();
+        recorder.begin_recording(bounds, None);
+        Recorder { recorder }
+    }
+
+    /// Replays a previously recorded `Picture` onto the live surface.
+    pub fn draw_picture(
+        &mut self,
+        picture: &Picture,
+        matrix: Option<&skia_safe::Matrix>,
+        paint: Option<&Paint>,
+    ) {
+        let span = span!(Level::INFO, "Canvas::draw_picture");
+        let _guard = span.enter();
+        self.surface
+            .canvas()
+            .draw_picture(&picture.0, matrix, paint);
+    }
+
+    /// Draws a YUV planar video frame (e.g. straight off a decoder) without
+    /// forcing callers to convert to packed BGRA on the CPU first. On a
+    /// Metal canvas each plane is uploaded as its own single/two-channel GPU
+    /// texture and skia's shader does the color conversion and scaling
+    /// during the draw; without a Metal context this falls back to
+    /// converting to BGRA on the CPU. `planes`/`strides` must have one entry
+    /// per plane of `yuv_format` (2 for NV12, 3 for I420).
+    pub fn draw_yuv_planes(
+        &mut self,
+        width: u32,
+        height: u32,
+        planes: &[&[u8]],
+        strides: &[usize],
+        yuv_format: YuvFormat,
+        color_space: skia_safe::YUVColorSpace,
+        dst_rect: skia_safe::Rect,
+    ) -> bool {
+        let span = span!(Level::INFO, "Canvas::draw_yuv_planes");
+        let _guard = span.enter();
+
+        if planes.len() != yuv_format.plane_count() || strides.len() != planes.len() {
+            event!(
+                Level::ERROR,
+                "draw_yuv_planes: expected {} planes/strides for {:?}, got {}/{}",
+                yuv_format.plane_count(),
+                yuv_format,
+                planes.len(),
+                strides.len()
+            );
+            return false;
+        }
+
+        // Plane 0 (luma) is full resolution; the rest are 4:2:0
+        // half-resolution chroma, in both `YuvFormat`s this module knows
+        // about. Check each plane is at least `stride * rows` before we let
+        // the CPU conversion index into it or hand the raw slice to the GPU
+        // pixmap path, the same class of bounds check `update_texture` does
+        // for its own upload buffer.
+        for (i, plane) in planes.iter().enumerate() {
+            let rows = if i == 0 { height } else { (height + 1) / 2 };
+            let required_bytes = strides[i] * rows as usize;
+            if plane.len() < required_bytes {
+                event!(
+                    Level::ERROR,
+                    "draw_yuv_planes: plane {} is {} bytes, need at least {} for stride {} x {} rows",
+                    i,
+                    plane.len(),
+                    required_bytes,
+                    strides[i],
+                    rows
+                );
+                return false;
+            }
+        }
+
+        #[cfg(target_os = "macos")]
+        if self._context.is_some() {
+            return self.draw_yuv_planes_gpu(
+                width, height, planes, strides, yuv_format, color_space, dst_rect,
+            );
+        }
+
+        self.draw_yuv_planes_cpu(width, height, planes, strides, yuv_format, color_space, dst_rect)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn draw_yuv_planes_gpu(
+        &mut self,
+        width: u32,
+        height: u32,
+        planes: &[&[u8]],
+        strides: &[usize],
+        yuv_format: YuvFormat,
+        color_space: skia_safe::YUVColorSpace,
+        dst_rect: skia_safe::Rect,
+    ) -> bool {
+        use skia_safe::yuva_info::Subsampling;
+        use skia_safe::{AlphaType, ColorType, ImageInfo, Pixmap, YUVAInfo, YUVAPixmaps};
+
+        let yuva_info = YUVAInfo::new(
+            (width as i32, height as i32),
+            yuv_format.plane_config(),
+            Subsampling::K420,
+            color_space,
+            skia_safe::EncodedOrigin::TopLeft,
+            None,
+            None,
+        );
+
+        let plane_dimensions = yuva_info.plane_dimensions();
+        let mut pixmaps = Vec::with_capacity(planes.len());
+        for (i, plane) in planes.iter().enumerate() {
+            // NV12's second plane is interleaved U/V (two channels); every
+            // other plane, in both formats, is a lone luma/chroma channel.
+            let plane_color_type = if yuv_format == YuvFormat::Nv12 && i == 1 {
+                ColorType::R8G8UNorm
+            } else {
+                ColorType::Gray8
+            };
+            let info = ImageInfo::new(
+                plane_dimensions[i],
+                plane_color_type,
+                AlphaType::Opaque,
+                None,
+            );
+            pixmaps.push(Pixmap::new(&info, plane, strides[i]));
+        }
+
+        let Some(yuva_pixmaps) = YUVAPixmaps::from_external_memory(&yuva_info, &pixmaps) else {
+            event!(Level::ERROR, "draw_yuv_planes: failed to describe YUVA planes.");
+            return false;
+        };
+
+        let context = self._context.as_mut().unwrap();
+        let Some(image) = Image::from_yuva_pixmaps(
+            context,
+            &yuva_pixmaps,
+            skia_safe::gpu::Mipmapped::No,
+            false,
+            None,
+        ) else {
+            event!(
+                Level::ERROR,
+                "draw_yuv_planes: failed to build a GPU image from YUV planes."
+            );
+            return false;
+        };
+
+        let options = SamplingOptions::new(
+            skia_safe::FilterMode::Linear,
+            skia_safe::MipmapMode::Nearest,
+        );
+        let paint = self.paint.clone();
+        self.surface
+            .canvas()
+            .draw_image_rect_with_sampling_options(&image, None, dst_rect, options, &paint);
+        true
+    }
+
+    fn draw_yuv_planes_cpu(
+        &mut self,
+        width: u32,
+        height: u32,
+        planes: &[&[u8]],
+        strides: &[usize],
+        yuv_format: YuvFormat,
+        color_space: skia_safe::YUVColorSpace,
+        dst_rect: skia_safe::Rect,
+    ) -> bool {
+        let bgra = yuv_planes_to_bgra(width, height, planes, strides, yuv_format, color_space);
+
+        let info = skia_safe::ImageInfo::new(
+            skia_safe::ISize::new(width as i32, height as i32),
+            skia_safe::ColorType::BGRA8888,
+            skia_safe::AlphaType::Premul,
+            None,
+        );
+        let sdata = unsafe { skia_safe::Data::new_bytes(&bgra) };
+        let Some(image) =
+            Image::from_raster_data(&info, sdata, width as usize * mem::size_of::<u32>())
+        else {
+            event!(
+                Level::ERROR,
+                "draw_yuv_planes: failed to build a CPU image from converted pixels."
+            );
+            return false;
+        };
+
+        let options = SamplingOptions::new(
+            skia_safe::FilterMode::Linear,
+            skia_safe::MipmapMode::Nearest,
+        );
+        let paint = self.paint.clone();
+        self.surface
+            .canvas()
+            .draw_image_rect_with_sampling_options(&image, None, dst_rect, options, &paint);
+        true
+    }
+}
+
+// Straightforward CPU YUV -> BGRA conversion used when no Metal context is
+// available. Every 2x2 luma block shares one chroma sample, matching 4:2:0
+// subsampling (the only layout `YuvFormat` describes today).
+fn yuv_planes_to_bgra(
+    width: u32,
+    height: u32,
+    planes: &[&[u8]],
+    strides: &[usize],
+    yuv_format: YuvFormat,
+    color_space: skia_safe::YUVColorSpace,
+) -> Vec<u8> {
+    // NV12 has a single interleaved U/V plane, so the same stride describes
+    // both; I420 keeps U and V as fully separate planes that may have been
+    // allocated (and strided) independently, so each needs its own stride.
+    let (y_plane, u_plane, v_plane, u_stride, v_stride) = match yuv_format {
+        YuvFormat::Nv12 => (planes[0], planes[1], planes[1], strides[1], strides[1]),
+        YuvFormat::I420 => (planes[0], planes[1], planes[2], strides[1], strides[2]),
+    };
+
+    // BT.601 vs. BT.709 luma/chroma coefficients; anything else we haven't
+    // special-cased falls back to the BT.601 coefficients used by most SD
+    // and webcam content.
+    let (kr, kb) = match color_space {
+        skia_safe::YUVColorSpace::Rec709 => (0.2126, 0.0722),
+        _ => (0.299, 0.114),
+    };
+
+    let mut bgra = vec![0u8; width as usize * height as usize * 4];
+    for row in 0..height as usize {
+        let y_row = &y_plane[row * strides[0]..];
+        let chroma_row = row / 2;
+        for col in 0..width as usize {
+            let y = y_row[col] as f32;
+            let (u, v) = match yuv_format {
+                YuvFormat::Nv12 => {
+                    let idx = chroma_row * u_stride + (col / 2) * 2;
+                    (u_plane[idx] as f32, v_plane[idx + 1] as f32)
+                }
+                YuvFormat::I420 => {
+                    let u_idx = chroma_row * u_stride + col / 2;
+                    let v_idx = chroma_row * v_stride + col / 2;
+                    (u_plane[u_idx] as f32, v_plane[v_idx] as f32)
+                }
+            };
+
+            let y = y - 16.0;
+            let u = u - 128.0;
+            let v = v - 128.0;
+
+            let r = 1.164 * y + 2.0 * (1.0 - kr) * v;
+            let b = 1.164 * y + 2.0 * (1.0 - kb) * u;
+            let g = 1.164 * y - 2.0 * kb * (1.0 - kb) / (1.0 - kb - kr) * u
+                - 2.0 * kr * (1.0 - kr) / (1.0 - kb - kr) * v;
+
+            let out = (row * width as usize + col) * 4;
+            bgra[out] = b.clamp(0.0, 255.0) as u8;
+            bgra[out + 1] = g.clamp(0.0, 255.0) as u8;
+            bgra[out + 2] = r.clamp(0.0, 255.0) as u8;
+            bgra[out + 3] = 255;
+        }
+    }
+    bgra
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yuv_to_bgra_mid_gray_is_neutral() {
+        let (width, height) = (4, 4);
+        let y = vec![128u8; (width * height) as usize];
+        let u = vec![128u8; (width / 2 * height / 2) as usize];
+        let v = vec![128u8; (width / 2 * height / 2) as usize];
+        let planes: [&[u8]; 3] = [&y, &u, &v];
+        let strides = [width as usize, (width / 2) as usize, (width / 2) as usize];
+
+        let bgra = yuv_planes_to_bgra(
+            width,
+            height,
+            &planes,
+            &strides,
+            YuvFormat::I420,
+            skia_safe::YUVColorSpace::Rec601,
+        );
+
+        for pixel in bgra.chunks_exact(4) {
+            assert_eq!(pixel[0], pixel[1]);
+            assert_eq!(pixel[1], pixel[2]);
+            assert_eq!(pixel[3], 255);
+        }
+    }
+
+    // Regression test for the two stride bugs fixed in a follow-up commit:
+    // I420's U and V planes can be padded/allocated independently and must
+    // each be read with their own stride, not the Y plane's.
+    #[test]
+    fn yuv_to_bgra_i420_uses_each_chroma_planes_own_stride() {
+        let (width, height) = (4, 4);
+        let y = vec![128u8; (width * height) as usize];
+        // Two chroma rows of 2 logical samples each, padded differently (one
+        // padding byte per U row, two per V row), so the Y, U and V strides
+        // are all distinct.
+        let u_stride = width as usize / 2 + 1;
+        let v_stride = width as usize / 2 + 2;
+        let mut u = vec![0u8; u_stride * 2];
+        let mut v = vec![0u8; v_stride * 2];
+        // Second chroma row (luma rows 2-3) is u=v=240; the first chroma row
+        // and all padding stay 0.
+        u[u_stride..u_stride + 2].fill(240);
+        v[v_stride..v_stride + 2].fill(240);
+        let planes: [&[u8]; 3] = [&y, &u, &v];
+        let strides = [width as usize, u_stride, v_stride];
+
+        let bgra = yuv_planes_to_bgra(
+            width,
+            height,
+            &planes,
+            &strides,
+            YuvFormat::I420,
+            skia_safe::YUVColorSpace::Rec601,
+        );
+
+        // Luma row 2 reads the second chroma row, so its left (columns 0-1)
+        // and right (columns 2-3) halves both see u=v=240, unlike row 0.
+        // Reading U with the Y plane's stride (4) lands the right half on
+        // U's padding, and reading V with U's stride (3) lands the left half
+        // on V's padding, so either bug makes the two halves differ.
+        let row_bytes = width as usize * 4;
+        let row0_left = &bgra[0..4];
+        let row2_left = &bgra[2 * row_bytes..2 * row_bytes + 4];
+        let row2_right = &bgra[2 * row_bytes + 8..2 * row_bytes + 12];
+        assert_eq!(row2_left, row2_right);
+        assert_ne!(row0_left, row2_left);
+    }
+
+    #[test]
+    fn yuv_to_bgra_nv12_interleaved_uv() {
+        let (width, height) = (2, 2);
+        let y = vec![128u8; (width * height) as usize];
+        // Interleaved U,V pair for the single 2x2 chroma block.
+        let uv = vec![128u8, 128u8];
+        let planes: [&[u8]; 2] = [&y, &uv];
+        let strides = [width as usize, width as usize];
+
+        let bgra = yuv_planes_to_bgra(
+            width,
+            height,
+            &planes,
+            &strides,
+            YuvFormat::Nv12,
+            skia_safe::YUVColorSpace::Rec601,
+        );
+
+        for pixel in bgra.chunks_exact(4) {
+            assert_eq!(pixel[0], pixel[1]);
+            assert_eq!(pixel[1], pixel[2]);
+            assert_eq!(pixel[3], 255);
+        }
+    }
+
+    #[test]
+    fn picture_record_serialize_deserialize_draw_round_trip() {
+        let mut canvas = Canvas::new(8, 8);
+
+        let mut recorder = canvas.begin_recording();
+        let mut fill_paint = Paint::default();
+        fill_paint.set_color(Color::BLUE);
+        recorder
+            .canvas()
+            .draw_rect(skia_safe::Rect::new(0.0, 0.0, 8.0, 8.0), &fill_paint);
+        let picture = recorder.finish_recording().expect("picture was recorded");
+
+        let data = picture.serialize();
+        let picture = Picture::deserialize(&data).expect("picture round-trips through bytes");
+
+        canvas.draw_picture(&picture, None, None);
+
+        let pixmap = canvas
+            .surface
+            .peek_pixels()
+            .expect("raster surface pixels are readable");
+        assert_eq!(pixmap.get_color((0, 0)), Color::BLUE);
+    }
+
+    #[test]
+    fn finish_recording_with_no_draws_returns_none() {
+        let canvas = Canvas::new(8, 8);
+        let recorder = canvas.begin_recording();
+        assert!(recorder.finish_recording().is_none());
+    }
+}
+
+/// A recording in progress, started by `Canvas::begin_recording()`.
+pub struct Recorder {
+    recorder: skia_safe::PictureRecorder,
+}
+
+impl Recorder {
+    /// The canvas to draw the recorded commands onto.
+    #[inline]
+    pub fn canvas(&mut self) -> &mut skia_safe::Canvas {
+        self.recorder
+            .recording_canvas()
+            .expect("Recorder always has an active recording")
+    }
+
+    /// Stops recording and returns the captured draw ops as a replayable
+    /// `Picture`, or `None` if nothing was ever drawn.
+    pub fn finish_recording(mut self) -> Option<Picture> {
+        let span = span!(Level::INFO, "Recorder::finish_recording");
+        let _guard = span.enter();
+        self.recorder
+            .finish_recording_as_picture(None)
+            .filter(|picture| picture.approximate_op_count() > 0)
+            .map(Picture)
+    }
+}
+
+/// A captured, replayable stream of draw commands. Can be replayed with
+/// `Canvas::draw_picture()`, or serialized to bytes and replayed later or on
+/// another device.
+pub struct Picture(skia_safe::Picture);
+
+impl Picture {
+    /// Serializes the picture to bytes for caching or transport.
+    pub fn serialize(&self) -> skia_safe::Data {
+        self.0.serialize()
+    }
+
+    /// Deserializes a picture previously produced by `serialize()`.
+    pub fn deserialize(data: &skia_safe::Data) -> Option<Picture> {
+        skia_safe::Picture::from_data(data, None).map(Picture)
+    }
 }
@@ -0,0 +1,381 @@
+//! Frame profiler, modeled on WebRender's consolidated profiler: every
+//! counter lives uniformly in a fixed array of ring buffers, each reporting
+//! a rolling average and max over its window. Not every counter gets a
+//! sample on every frame (e.g. image uploads only happen when a frame
+//! actually uploads new pixels), so ring buffers track how many samples
+//! they've actually seen rather than assuming a fixed cadence.
+
+use skia_safe::{Canvas as SkCanvas, Color, Font, Paint, Point, Rect};
+use std::time::Instant;
+
+const HISTORY_LEN: usize = 128;
+
+/// A 16ms (60fps) and 33ms (30fps) budget line are drawn on the GPU submit
+/// time graph so regressions past either threshold are obvious at a glance.
+const GPU_BUDGET_60FPS_MS: f64 = 16.0;
+const GPU_BUDGET_30FPS_MS: f64 = 33.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Counter {
+    /// Wall-clock time from draw entry to flush-and-submit returning, in ms.
+    CpuFrameTime,
+    /// Wall-clock time spent inside the GPU submit call itself, in ms.
+    GpuSubmitTime,
+    /// Change in process virtual memory since the previous frame, in MB.
+    VMemDelta,
+    /// Growth in process virtual memory since the first sampled frame, in
+    /// MB. Unlike `VMemDelta` this is monotonic for a real leak, so it's
+    /// what regression guards should threshold against: a steady per-frame
+    /// leak shows up in `VMemDelta` only as a small, constant per-frame
+    /// value, but keeps climbing here.
+    VMemCumulative,
+    /// Bytes of pixel data uploaded to the GPU this frame.
+    ImageUploadBytes,
+}
+
+const ALL_COUNTERS: [Counter; 5] = [
+    Counter::CpuFrameTime,
+    Counter::GpuSubmitTime,
+    Counter::VMemDelta,
+    Counter::VMemCumulative,
+    Counter::ImageUploadBytes,
+];
+
+impl Counter {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Counter::CpuFrameTime => "cpu frame (ms)",
+            Counter::GpuSubmitTime => "gpu submit (ms)",
+            Counter::VMemDelta => "vmem delta (MB)",
+            Counter::VMemCumulative => "vmem total (MB)",
+            Counter::ImageUploadBytes => "image upload (B)",
+        }
+    }
+
+    fn short_name(self) -> &'static str {
+        match self {
+            Counter::CpuFrameTime => "cpu",
+            Counter::GpuSubmitTime => "gpu",
+            Counter::VMemDelta => "vmem",
+            Counter::VMemCumulative => "vmem_total",
+            Counter::ImageUploadBytes => "upload",
+        }
+    }
+
+    fn from_short_name(name: &str) -> Option<Counter> {
+        ALL_COUNTERS
+            .iter()
+            .copied()
+            .find(|counter| counter.short_name() == name)
+    }
+}
+
+/// Which counters `Profiler::overlay()` should draw. A plain bitset so
+/// callers can toggle counters from a pref without matching on `Counter`
+/// directly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CounterSet(u8);
+
+impl CounterSet {
+    pub const NONE: CounterSet = CounterSet(0);
+    pub const ALL: CounterSet = CounterSet(0b1_1111);
+
+    pub fn with(mut self, counter: Counter) -> Self {
+        self.0 |= 1 << counter.index();
+        self
+    }
+
+    pub fn contains(self, counter: Counter) -> bool {
+        self.0 & (1 << counter.index()) != 0
+    }
+
+    /// Parses a comma-separated counter name list, e.g. `"cpu,gpu"`.
+    /// Unknown names are ignored.
+    pub fn parse(pref: &str) -> Self {
+        pref.split(',')
+            .filter_map(|name| Counter::from_short_name(name.trim()))
+            .fold(CounterSet::NONE, CounterSet::with)
+    }
+}
+
+struct Ring {
+    samples: [f64; HISTORY_LEN],
+    write_at: usize,
+    len: usize,
+}
+
+impl Ring {
+    fn new() -> Self {
+        Ring {
+            samples: [0.0; HISTORY_LEN],
+            write_at: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, value: f64) {
+        self.samples[self.write_at] = value;
+        self.write_at = (self.write_at + 1) % HISTORY_LEN;
+        self.len = (self.len + 1).min(HISTORY_LEN);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..self.len).map(move |i| {
+            let idx = (self.write_at + HISTORY_LEN - self.len + i) % HISTORY_LEN;
+            self.samples[idx]
+        })
+    }
+
+    fn average(&self) -> f64 {
+        if self.len == 0 {
+            return 0.0;
+        }
+        self.iter().sum::<f64>() / self.len as f64
+    }
+
+    fn max(&self) -> f64 {
+        self.iter().fold(0.0, f64::max)
+    }
+}
+
+/// Rolling-window frame profiler. Attach one to a `Canvas` and feed it
+/// timing/byte samples as frames happen; `overlay()` renders the selected
+/// counters directly onto a surface.
+pub struct Profiler {
+    rings: [Ring; ALL_COUNTERS.len()],
+    visible: CounterSet,
+    frame_start: Option<Instant>,
+    gpu_submit_start: Option<Instant>,
+    last_vmem_mb: Option<f64>,
+    baseline_vmem_mb: Option<f64>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            rings: [
+                Ring::new(),
+                Ring::new(),
+                Ring::new(),
+                Ring::new(),
+                Ring::new(),
+            ],
+            visible: CounterSet::ALL,
+            frame_start: None,
+            gpu_submit_start: None,
+            last_vmem_mb: None,
+            baseline_vmem_mb: None,
+        }
+    }
+
+    /// Selects which counters `overlay()` draws.
+    pub fn set_visible_counters(&mut self, visible: CounterSet) {
+        self.visible = visible;
+    }
+
+    /// Marks the start of a frame's CPU work.
+    pub fn begin_frame(&mut self) {
+        self.frame_start = Some(Instant::now());
+    }
+
+    /// Marks the end of a frame's CPU work (after flush-and-submit returns)
+    /// and records the CPU frame time, VMem delta and cumulative-VMem-growth
+    /// samples.
+    pub fn end_frame(&mut self) {
+        if let Some(start) = self.frame_start.take() {
+            self.record(Counter::CpuFrameTime, start.elapsed().as_secs_f64() * 1000.0);
+        }
+
+        if let Some(usage) = memory_stats::memory_stats() {
+            let vmem_mb = usage.virtual_mem as f64 / 1024.0 / 1024.0;
+            let baseline = *self.baseline_vmem_mb.get_or_insert(vmem_mb);
+            if let Some(last) = self.last_vmem_mb {
+                self.record(Counter::VMemDelta, vmem_mb - last);
+            }
+            self.record(Counter::VMemCumulative, vmem_mb - baseline);
+            self.last_vmem_mb = Some(vmem_mb);
+        }
+    }
+
+    /// Marks the start of a GPU submit (e.g. `flush_and_submit`).
+    pub fn begin_gpu_submit(&mut self) {
+        self.gpu_submit_start = Some(Instant::now());
+    }
+
+    /// Marks the end of a GPU submit and records the sample.
+    pub fn end_gpu_submit(&mut self) {
+        if let Some(start) = self.gpu_submit_start.take() {
+            self.record(
+                Counter::GpuSubmitTime,
+                start.elapsed().as_secs_f64() * 1000.0,
+            );
+        }
+    }
+
+    /// Records one sample for a counter. Counters that don't get a sample
+    /// every frame (image uploads, VMem deltas before the first frame) just
+    /// go untouched that frame; their average/max reflect only the samples
+    /// actually recorded.
+    pub fn record(&mut self, counter: Counter, value: f64) {
+        self.rings[counter.index()].push(value);
+    }
+
+    pub fn average(&self, counter: Counter) -> f64 {
+        self.rings[counter.index()].average()
+    }
+
+    pub fn max(&self, counter: Counter) -> f64 {
+        self.rings[counter.index()].max()
+    }
+
+    /// Draws the visible counters as "label: avg/max" text with a small
+    /// history graph beneath each, stacked top-down starting at `origin`.
+    /// The GPU submit graph auto-scales to whichever is bigger: its own
+    /// recent max, or the 33ms budget line, so a well-behaved frame doesn't
+    /// pin the graph to the ceiling.
+    pub fn overlay(&self, skia_canvas: &mut SkCanvas, origin: Point) {
+        const ROW_HEIGHT: f32 = 36.0;
+        const GRAPH_HEIGHT: f32 = 20.0;
+
+        let mut text_paint = Paint::default();
+        text_paint.set_color(Color::GREEN);
+        text_paint.set_anti_alias(true);
+        let font = Font::default();
+
+        let mut y = origin.y;
+        for &counter in &ALL_COUNTERS {
+            if !self.visible.contains(counter) {
+                continue;
+            }
+            let ring = &self.rings[counter.index()];
+            let label = format!(
+                "{}: avg {:.2} max {:.2}",
+                counter.label(),
+                ring.average(),
+                ring.max()
+            );
+            skia_canvas.draw_str(&label, Point::new(origin.x, y), &font, &text_paint);
+
+            let graph_top = y + 4.0;
+            let scale_max = if counter == Counter::GpuSubmitTime {
+                ring.max().max(GPU_BUDGET_30FPS_MS)
+            } else {
+                ring.max().max(1.0)
+            };
+            draw_graph(skia_canvas, ring, origin.x, graph_top, GRAPH_HEIGHT, scale_max);
+
+            if counter == Counter::GpuSubmitTime {
+                draw_budget_line(
+                    skia_canvas,
+                    origin.x,
+                    graph_top,
+                    GRAPH_HEIGHT,
+                    scale_max,
+                    GPU_BUDGET_60FPS_MS,
+                    Color::YELLOW,
+                );
+                draw_budget_line(
+                    skia_canvas,
+                    origin.x,
+                    graph_top,
+                    GRAPH_HEIGHT,
+                    scale_max,
+                    GPU_BUDGET_30FPS_MS,
+                    Color::RED,
+                );
+            }
+
+            y += ROW_HEIGHT;
+        }
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn draw_graph(skia_canvas: &mut SkCanvas, ring: &Ring, x: f32, y: f32, height: f32, scale_max: f64) {
+    let mut paint = Paint::default();
+    paint.set_color(Color::CYAN);
+    paint.set_anti_alias(true);
+
+    for (i, value) in ring.iter().enumerate() {
+        let bar_height = (value / scale_max).clamp(0.0, 1.0) as f32 * height;
+        let bar_x = x + i as f32;
+        skia_canvas.draw_rect(
+            Rect::new(bar_x, y + height - bar_height, bar_x + 1.0, y + height),
+            &paint,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_average_and_max_ignore_unwritten_slots() {
+        let mut ring = Ring::new();
+        assert_eq!(ring.average(), 0.0);
+        assert_eq!(ring.max(), 0.0);
+
+        ring.push(2.0);
+        ring.push(4.0);
+        ring.push(6.0);
+        assert_eq!(ring.average(), 4.0);
+        assert_eq!(ring.max(), 6.0);
+    }
+
+    #[test]
+    fn ring_drops_oldest_sample_past_history_len() {
+        let mut ring = Ring::new();
+        for i in 0..HISTORY_LEN + 10 {
+            ring.push(i as f64);
+        }
+        // Only the most recent HISTORY_LEN samples survive.
+        assert_eq!(ring.iter().count(), HISTORY_LEN);
+        assert_eq!(ring.max(), (HISTORY_LEN + 9) as f64);
+        assert_eq!(ring.iter().next(), Some(10.0));
+    }
+
+    #[test]
+    fn counter_set_parse_known_and_unknown_names() {
+        let set = CounterSet::parse("cpu, vmem_total, bogus");
+        assert!(set.contains(Counter::CpuFrameTime));
+        assert!(set.contains(Counter::VMemCumulative));
+        assert!(!set.contains(Counter::GpuSubmitTime));
+        assert!(!set.contains(Counter::VMemDelta));
+        assert!(!set.contains(Counter::ImageUploadBytes));
+    }
+
+    #[test]
+    fn counter_set_parse_empty_is_none() {
+        assert_eq!(CounterSet::parse(""), CounterSet::NONE);
+    }
+}
+
+fn draw_budget_line(
+    skia_canvas: &mut SkCanvas,
+    x: f32,
+    graph_top: f32,
+    graph_height: f32,
+    scale_max: f64,
+    budget_ms: f64,
+    color: Color,
+) {
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    let frac = (budget_ms / scale_max).clamp(0.0, 1.0) as f32;
+    let line_y = graph_top + graph_height - frac * graph_height;
+    skia_canvas.draw_line(
+        Point::new(x, line_y),
+        Point::new(x + HISTORY_LEN as f32, line_y),
+        &paint,
+    );
+}